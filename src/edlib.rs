@@ -102,6 +102,54 @@ pub struct EdlibEqualityPairRs {
 }
 
 
+impl EdlibEqualityPairRs {
+    /// Builds a single equality pair declaring `first` and `second` as equal.
+    pub fn new(first : char, second : char) -> Self {
+        EdlibEqualityPairRs{first : first as u8 as c_char, second : second as u8 as c_char}
+    }
+}
+
+
+/// Builds the equality pairs needed to make alignment case-insensitive : each ASCII letter
+/// is declared equal to its opposite case counterpart.
+/// The returned `Vec` can be passed directly as the `additionalequalities` argument of
+/// `EdlibAlignConfigRs::new`.
+pub fn case_insensitive_equalities() -> Vec<EdlibEqualityPairRs> {
+    (b'a'..=b'z').map(|lower| {
+        let upper = lower.to_ascii_uppercase();
+        EdlibEqualityPairRs::new(lower as char, upper as char)
+    }).collect()
+}
+
+
+/// Builds the equality pairs expanding the IUPAC nucleotide ambiguity codes, so that e.g.
+/// 'N' is declared equal to 'A', 'C', 'G' and 'T', 'R' to 'A'/'G', 'Y' to 'C'/'T', and so on
+/// for the full table (see https://www.bioinformatics.org/sms/iupac.html).
+/// Lets bioinformatics users align reads with ambiguous bases without building the
+/// equality table by hand.
+pub fn iupac_equalities() -> Vec<EdlibEqualityPairRs> {
+    let table : &[(char, &str)] = &[
+        ('R', "AG"),
+        ('Y', "CT"),
+        ('S', "GC"),
+        ('W', "AT"),
+        ('K', "GT"),
+        ('M', "AC"),
+        ('B', "CGT"),
+        ('D', "AGT"),
+        ('H', "ACT"),
+        ('V', "ACG"),
+        ('N', "ACGT"),
+    ];
+    let mut pairs = Vec::new();
+    for &(code, bases) in table {
+        for base in bases.chars() {
+            pairs.push(EdlibEqualityPairRs::new(code, base));
+        }
+    }
+    pairs
+}
+
 
 //=================================================================================================
 /// 
@@ -191,7 +239,7 @@ pub struct EdlibAlignResultRs {
     numLocations : usize,
 
     /// Alignment is found for first pair of start and end locations.
-    /// Set to NULL if not calculated.
+    /// Set to None if not calculated.
     /// Alignment is sequence of numbers: 0, 1, 2, 3.
     /// 0 stands for match.
     /// 1 stands for insertion to target.
@@ -199,7 +247,8 @@ pub struct EdlibAlignResultRs {
     /// 3 stands for mismatch.
     /// Alignment aligns query to target from begining of query till end of query.
     /// If gaps are not penalized, they are not in alignment.
-    alignment : Option<Vec<char>>,
+    /// Kept as raw op codes (rather than char) so it can be fed directly into edlibAlignmentToCigarRs.
+    alignment : Option<Vec<u8>>,
 
     /// Length of alignment.
     alignmentLength : u32,
@@ -227,6 +276,55 @@ impl Default for  EdlibAlignResultRs {
 
 
 
+impl EdlibAlignResultRs {
+
+    /// Edlib's own issue tracker notes that in HW/SHW mode the optimal alignment can place the
+    /// query ahead of the target, yielding an end location of -1 (and start locations that are
+    /// then meaningless even when not themselves negative). This returns only the end locations
+    /// that actually fall within target (i.e. are non-negative), filtering out such before-target
+    /// overhangs.
+    pub fn in_target_end_locations(&self) -> Option<Vec<i32>> {
+        self.endLocations.as_ref().map(|locations| locations.iter().copied().filter(|&loc| loc >= 0).collect())
+    }
+
+    /// Start locations filtered by the *same* index predicate as
+    /// [in_target_end_locations](EdlibAlignResultRs::in_target_end_locations) (end location
+    /// non-negative), rather than by their own sign, so the result stays paired with it:
+    /// start and end locations at the same index describe the same alignment, and a start
+    /// location is only meaningful when its corresponding end location is in-target.
+    pub fn in_target_start_locations(&self) -> Option<Vec<i32>> {
+        let starts = self.startLocations.as_ref()?;
+        let ends = self.endLocations.as_ref()?;
+        Some(starts.iter().zip(ends.iter())
+                .filter(|&(_, &end)| end >= 0)
+                .map(|(&start, _)| start)
+                .collect())
+    }
+
+    /// Paired (start, end) in-target locations, i.e. the rows of `startLocations`/`endLocations`
+    /// whose end location is non-negative. Prefer this over calling
+    /// [in_target_start_locations](EdlibAlignResultRs::in_target_start_locations) and
+    /// [in_target_end_locations](EdlibAlignResultRs::in_target_end_locations) separately, since
+    /// it guarantees the two sides stay zipped.
+    pub fn in_target_locations(&self) -> Option<Vec<(i32, i32)>> {
+        let starts = self.startLocations.as_ref()?;
+        let ends = self.endLocations.as_ref()?;
+        Some(starts.iter().zip(ends.iter())
+                .filter(|&(_, &end)| end >= 0)
+                .map(|(&start, &end)| (start, end))
+                .collect())
+    }
+
+    /// True when at least one end location is negative, meaning that the optimal alignment
+    /// (in HW/SHW mode) places the query, or part of it, before the start of target.
+    /// [EdlibNiceAlignmentRs::new] returns None when this is true; edlibAlignmentToCigarRs works
+    /// on the alignment path only and does not look at locations.
+    pub fn has_before_target_overhang(&self) -> bool {
+        self.endLocations.as_ref().map_or(false, |locations| locations.iter().any(|&loc| loc < 0))
+    }
+}  // end impl EdlibAlignResultRs
+
+
 
     
     /// Aligns two sequences (query and target) using edit distance (levenshtein distance).
@@ -253,6 +351,11 @@ impl Default for  EdlibAlignResultRs {
             EdlibAlignModeRs::EDLIB_MODE_SHW => 1,
             EdlibAlignModeRs::EDLIB_MODE_HW => 2,
         };
+        config_c.task = match config_rs.task {
+            EdlibAlignTaskRs::EDLIB_TASK_DISTANCE => 0,
+            EdlibAlignTaskRs::EDLIB_TASK_LOC => 1,
+            EdlibAlignTaskRs::EDLIB_TASK_PATH => 2,
+        };
         config_c.additionalEqualitiesLength = config_rs.additionalequalities.len() as ::std::os::raw::c_int;
         if config_c.additionalEqualitiesLength > 0 {
             config_c.additionalEqualities = config_rs.additionalequalities.as_ptr() as *const EdlibEqualityPair;
@@ -277,9 +380,25 @@ impl Default for  EdlibAlignResultRs {
         if align_res_rs.numLocations > 0 {
             let s_end = unsafe { slice::from_raw_parts(res_c.endLocations, align_res_rs.numLocations) };
             align_res_rs.endLocations = Some(s_end.into_iter().map(|l| *l as i32).collect());
+        }
+        // startLocations is only populated for EDLIB_TASK_LOC/EDLIB_TASK_PATH, so it stays null
+        // for EDLIB_TASK_DISTANCE and must be guarded the same way as the alignment path pointer.
+        if align_res_rs.numLocations > 0 && !res_c.startLocations.is_null() {
             let s_start = unsafe { slice::from_raw_parts(res_c.startLocations, align_res_rs.numLocations) };
             align_res_rs.startLocations = Some(s_start.into_iter().map(|l| *l as i32).collect());
         }
+        // status, editDistance apart, all other fields have undefined values on error, so only
+        // trust alignmentLength/alphabetLength/alignment (and the raw-pointer-plus-length read
+        // they drive) when edlib actually reports success.
+        if align_res_rs.status == EDLIB_STATUS_OK {
+            align_res_rs.alignmentLength = res_c.alignmentLength as u32;
+            align_res_rs.alphabetLength = res_c.alphabetLength as u32;
+            // alignment path is only set for EDLIB_TASK_PATH and when edit distance <= k
+            if align_res_rs.alignmentLength > 0 && !res_c.alignment.is_null() {
+                let s_alignment = unsafe { slice::from_raw_parts(res_c.alignment, align_res_rs.alignmentLength as usize) };
+                align_res_rs.alignment = Some(s_alignment.to_vec());
+            }
+        }
         // Free C datas
         unsafe { edlibFreeAlignResult(res_c); };
         //
@@ -288,7 +407,28 @@ impl Default for  EdlibAlignResultRs {
 
 
 
-    
+    /// Convenience one-call Levenshtein distance between `query` and `target`, mirroring the
+    /// `distance($query, $target, [$max])` entry point found in the Perl binding.
+    /// `max` bounds the edit distance (passed as `k`) for an early exit; `None` lets edlib
+    /// auto-adjust `k` until the score is found.
+    /// Returns `None` if the alignment status is not OK, or if the edit distance exceeds `max`.
+    pub fn distance(query : &[u8], target : &[u8], max : Option<i32>) -> Option<i32> {
+        let config = EdlibAlignConfigRs::new(max.unwrap_or(-1),
+                                                EdlibAlignModeRs::EDLIB_MODE_NW,
+                                                EdlibAlignTaskRs::EDLIB_TASK_DISTANCE,
+                                                &[]);
+        let align_res = edlibAlignRs(query, target, &config);
+        if align_res.status != EDLIB_STATUS_OK || align_res.editDistance == -1 {
+            None
+        }
+        else {
+            Some(align_res.editDistance)
+        }
+    }
+
+
+
+
     /// Builds cigar string from given alignment sequence.
     ///  @param [in] alignment  Alignment sequence.
     //  *     0 stands for match.
@@ -308,12 +448,320 @@ impl Default for  EdlibAlignResultRs {
     //  *     String is null terminated.
     //  *     Needed memory is allocated and given pointer is set to it.
     //  *     Do not forget to free it later using free()!
-    // 
-    pub fn edlibAlignmentToCigarRs(alignment : &[u8], cigarFormat : &EdlibCigarFormat) {
-        println!("not yet iplmeented");
+    //
+    /// Returns None if `alignment` contains an op code outside of 0..=3.
+    pub fn edlibAlignmentToCigarRs(alignment : &[u8], cigarFormat : EdlibCigarFormatRs) -> Option<String> {
+        let mut cigar = String::new();
+        if alignment.is_empty() {
+            return Some(cigar);
+        }
+        // move code -> letter mapping according to chosen format.
+        // Note: in standard format match (0) and mismatch (3) both map to 'M', so
+        // consecutive runs of those two codes must be merged into a single run.
+        let move_code = |op : u8| -> Option<char> {
+            match cigarFormat {
+                EdlibCigarFormatRs::EDLIB_CIGAR_STANDARD => {
+                    match op {
+                        0 => Some('M'),
+                        1 => Some('I'),
+                        2 => Some('D'),
+                        3 => Some('M'),
+                        _ => None,
+                    }
+                },
+                EdlibCigarFormatRs::EDLIB_CIGAR_EXTENDED => {
+                    match op {
+                        0 => Some('='),
+                        1 => Some('I'),
+                        2 => Some('D'),
+                        3 => Some('X'),
+                        _ => None,
+                    }
+                },
+            }
+        };
+        //
+        let mut last_letter = move_code(alignment[0])?;
+        let mut num_same = 1u32;
+        for op in &alignment[1..] {
+            let letter = move_code(*op)?;
+            if letter == last_letter {
+                num_same += 1;
+            }
+            else {
+                cigar.push_str(&num_same.to_string());
+                cigar.push(last_letter);
+                last_letter = letter;
+                num_same = 1;
+            }
+        }
+        cigar.push_str(&num_same.to_string());
+        cigar.push(last_letter);
+        //
+        Some(cigar)
+    }
+
+
+//================================================================================================
+
+/// Human readable pairwise alignment, similar to the `nice: true` formatted output offered
+/// by the Ruby binding of edlib.
+/// It is made of three rows of equal length : the aligned query, a middle row showing
+/// matches ('|'), mismatches ('.') and gaps (' '), and the aligned target.
+#[derive(Debug, Clone)]
+pub struct EdlibNiceAlignmentRs {
+    /// Query sequence, with '-' inserted where target has an extra (inserted) character.
+    pub query_row : String,
+    /// '|' for a match, '.' for a mismatch, ' ' facing a gap in either row.
+    pub match_row : String,
+    /// Target sequence, with '-' inserted where query has an extra (inserted) character.
+    pub target_row : String,
+}
 
+
+impl EdlibNiceAlignmentRs {
+
+    /// Builds the three aligned rows from the query, the target and an `EdlibAlignResultRs`
+    /// computed with `EDLIB_TASK_PATH` (so that it carries an alignment path).
+    /// The target is sliced starting at the first in-target start location, so that the
+    /// returned rows line up with the first reported start/end location pair.
+    /// Returns None if `align_res` does not carry an alignment path, if it reports a
+    /// before-target overhang (see `has_before_target_overhang`), for which the classic
+    /// three-line layout has no well defined start in target, or if the path turns out to be
+    /// malformed (out of range op code, or walking past the end of `query`/`target`).
+    pub fn new(query : &[u8], target : &[u8], align_res : &EdlibAlignResultRs) -> Option<Self> {
+        let alignment = align_res.alignment.as_ref()?;
+        if align_res.has_before_target_overhang() {
+            return None;
+        }
+        let start = align_res.in_target_start_locations()
+                    .and_then(|locations| locations.get(0).copied())
+                    .map(|loc| loc as usize)
+                    .unwrap_or(0);
+        //
+        let mut query_row = String::with_capacity(alignment.len());
+        let mut match_row = String::with_capacity(alignment.len());
+        let mut target_row = String::with_capacity(alignment.len());
+        let mut q_idx = 0usize;
+        let mut t_idx = start;
+        for &op in alignment.iter() {
+            match op {
+                0 | 3 => {
+                    if q_idx >= query.len() || t_idx >= target.len() {
+                        return None;
+                    }
+                    query_row.push(query[q_idx] as char);
+                    match_row.push(if op == 0 { '|' } else { '.' });
+                    target_row.push(target[t_idx] as char);
+                    q_idx += 1;
+                    t_idx += 1;
+                },
+                1 => {
+                    // insertion to target = deletion from query : query advances, target row gets a gap.
+                    if q_idx >= query.len() {
+                        return None;
+                    }
+                    query_row.push(query[q_idx] as char);
+                    match_row.push(' ');
+                    target_row.push('-');
+                    q_idx += 1;
+                },
+                2 => {
+                    // deletion from target = insertion to query : target advances, query row gets a gap.
+                    if t_idx >= target.len() {
+                        return None;
+                    }
+                    query_row.push('-');
+                    match_row.push(' ');
+                    target_row.push(target[t_idx] as char);
+                    t_idx += 1;
+                },
+                _ => return None,
+            }
+        }
+        //
+        Some(EdlibNiceAlignmentRs{query_row, match_row, target_row})
+    }
+
+    /// Renders the three rows, wrapping them every `width` columns so long alignments stay
+    /// readable on a terminal. Each block is separated by a blank line.
+    pub fn to_string_wrapped(&self, width : usize) -> String {
+        if width == 0 {
+            return format!("{}\n{}\n{}\n", self.query_row, self.match_row, self.target_row);
+        }
+        let query_chars : Vec<char> = self.query_row.chars().collect();
+        let match_chars : Vec<char> = self.match_row.chars().collect();
+        let target_chars : Vec<char> = self.target_row.chars().collect();
+        let mut res = String::new();
+        let mut pos = 0usize;
+        while pos < query_chars.len() {
+            let end = (pos + width).min(query_chars.len());
+            res.push_str(&query_chars[pos..end].iter().collect::<String>());
+            res.push('\n');
+            res.push_str(&match_chars[pos..end].iter().collect::<String>());
+            res.push('\n');
+            res.push_str(&target_chars[pos..end].iter().collect::<String>());
+            res.push('\n');
+            if end < query_chars.len() {
+                res.push('\n');
+            }
+            pos = end;
+        }
+        res
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cigar_standard_merges_match_and_mismatch_into_m() {
+        // 0=match, 3=mismatch : both map to 'M' in standard format and must merge into one run.
+        let alignment = [0u8, 0, 3, 0, 1, 1, 2];
+        let cigar = edlibAlignmentToCigarRs(&alignment, EdlibCigarFormatRs::EDLIB_CIGAR_STANDARD).unwrap();
+        assert_eq!(cigar, "4M2I1D");
+    }
+
+    #[test]
+    fn cigar_extended_keeps_match_and_mismatch_separate() {
+        let alignment = [0u8, 0, 3, 0, 1, 1, 2];
+        let cigar = edlibAlignmentToCigarRs(&alignment, EdlibCigarFormatRs::EDLIB_CIGAR_EXTENDED).unwrap();
+        assert_eq!(cigar, "2=1X1=2I1D");
+    }
+
+    #[test]
+    fn cigar_single_op() {
+        let cigar = edlibAlignmentToCigarRs(&[3u8], EdlibCigarFormatRs::EDLIB_CIGAR_STANDARD).unwrap();
+        assert_eq!(cigar, "1M");
+    }
+
+    #[test]
+    fn cigar_empty_alignment_is_empty_string() {
+        let cigar = edlibAlignmentToCigarRs(&[], EdlibCigarFormatRs::EDLIB_CIGAR_STANDARD).unwrap();
+        assert_eq!(cigar, "");
+    }
+
+    #[test]
+    fn cigar_invalid_op_code_returns_none() {
+        assert!(edlibAlignmentToCigarRs(&[0u8, 7], EdlibCigarFormatRs::EDLIB_CIGAR_STANDARD).is_none());
     }
 
+    #[test]
+    fn edlib_align_rs_with_task_path_populates_alignment() {
+        // drives edlibAlignRs (and so the C config task-wiring) end-to-end, rather than
+        // hand-constructing an EdlibAlignResultRs, so a regression in config_c.task is caught.
+        let config = EdlibAlignConfigRs::new(-1,
+                                                EdlibAlignModeRs::EDLIB_MODE_NW,
+                                                EdlibAlignTaskRs::EDLIB_TASK_PATH,
+                                                &[]);
+        let res = edlibAlignRs(b"ACGT", b"ACGT", &config);
+        assert!(res.alignment.is_some());
+        assert_eq!(res.alignmentLength as usize, res.alignment.as_ref().unwrap().len());
+        assert_eq!(res.alignment.unwrap(), vec![0u8, 0, 0, 0]);
+        assert!(res.startLocations.is_some());
+    }
+
+    #[test]
+    fn edlib_align_rs_with_task_distance_leaves_alignment_and_start_locations_none() {
+        let config = EdlibAlignConfigRs::new(-1,
+                                                EdlibAlignModeRs::EDLIB_MODE_NW,
+                                                EdlibAlignTaskRs::EDLIB_TASK_DISTANCE,
+                                                &[]);
+        let res = edlibAlignRs(b"ACGT", b"ACGT", &config);
+        assert!(res.alignment.is_none());
+        assert!(res.startLocations.is_none());
+        assert!(res.endLocations.is_some());
+    }
+
+    #[test]
+    fn nice_alignment_from_real_edlib_align_rs_task_path_result() {
+        // end-to-end : edlibAlignRs(EDLIB_TASK_PATH) feeding directly into EdlibNiceAlignmentRs::new,
+        // now that config_rs.task is actually forwarded to the C config.
+        let config = EdlibAlignConfigRs::new(-1,
+                                                EdlibAlignModeRs::EDLIB_MODE_NW,
+                                                EdlibAlignTaskRs::EDLIB_TASK_PATH,
+                                                &[]);
+        let res = edlibAlignRs(b"ACGT", b"ACGT", &config);
+        let nice = EdlibNiceAlignmentRs::new(b"ACGT", b"ACGT", &res).unwrap();
+        assert_eq!(nice.query_row, "ACGT");
+        assert_eq!(nice.match_row, "||||");
+        assert_eq!(nice.target_row, "ACGT");
+    }
+
+    fn align_result_with_path(alignment : Vec<u8>, start : i32, end : i32) -> EdlibAlignResultRs {
+        let mut res = EdlibAlignResultRs::default();
+        res.alignmentLength = alignment.len() as u32;
+        res.alignment = Some(alignment);
+        res.startLocations = Some(vec![start]);
+        res.endLocations = Some(vec![end]);
+        res.numLocations = 1;
+        res
+    }
+
+    #[test]
+    fn nice_alignment_builds_three_matching_rows() {
+        // query "ACGT" vs target "ACGT" : all matches.
+        let res = align_result_with_path(vec![0, 0, 0, 0], 0, 3);
+        let nice = EdlibNiceAlignmentRs::new(b"ACGT", b"ACGT", &res).unwrap();
+        assert_eq!(nice.query_row, "ACGT");
+        assert_eq!(nice.match_row, "||||");
+        assert_eq!(nice.target_row, "ACGT");
+    }
+
+    #[test]
+    fn nice_alignment_marks_gap_in_query_row() {
+        // query "ACT" vs target "ACGT" : A-match, C-match, insertion to query (gap in query)
+        // consuming target's G, then T-match.
+        let res = align_result_with_path(vec![0, 0, 2, 0], 0, 3);
+        let nice = EdlibNiceAlignmentRs::new(b"ACT", b"ACGT", &res).unwrap();
+        assert_eq!(nice.query_row, "AC-T");
+        assert_eq!(nice.match_row, "|| |");
+        assert_eq!(nice.target_row, "ACGT");
+    }
 
+    #[test]
+    fn nice_alignment_none_without_path() {
+        let res = EdlibAlignResultRs::default();
+        assert!(EdlibNiceAlignmentRs::new(b"AC", b"AC", &res).is_none());
+    }
+
+    #[test]
+    fn nice_alignment_none_on_before_target_overhang() {
+        let res = align_result_with_path(vec![0], 0, -1);
+        assert!(EdlibNiceAlignmentRs::new(b"A", b"A", &res).is_none());
+    }
 
+    fn pairs_as_char_tuples(pairs : &[EdlibEqualityPairRs]) -> Vec<(char, char)> {
+        pairs.iter().map(|p| (p.first as u8 as char, p.second as u8 as char)).collect()
+    }
+
+    #[test]
+    fn case_insensitive_equalities_covers_all_ascii_letters() {
+        let pairs = case_insensitive_equalities();
+        assert_eq!(pairs.len(), 26);
+        let as_chars = pairs_as_char_tuples(&pairs);
+        assert!(as_chars.contains(&('a', 'A')));
+        assert!(as_chars.contains(&('z', 'Z')));
+    }
 
+    #[test]
+    fn iupac_equalities_expand_n_to_all_four_bases() {
+        let pairs = iupac_equalities();
+        let as_chars = pairs_as_char_tuples(&pairs);
+        for base in ['A', 'C', 'G', 'T'] {
+            assert!(as_chars.contains(&('N', base)), "N should be paired with {}", base);
+        }
+    }
+
+    #[test]
+    fn iupac_equalities_expand_r_to_purines_only() {
+        let pairs = iupac_equalities();
+        let as_chars = pairs_as_char_tuples(&pairs);
+        assert!(as_chars.contains(&('R', 'A')));
+        assert!(as_chars.contains(&('R', 'G')));
+        assert!(!as_chars.contains(&('R', 'C')));
+        assert!(!as_chars.contains(&('R', 'T')));
+    }
+}